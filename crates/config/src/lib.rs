@@ -0,0 +1,37 @@
+//! Configuration shared by foundry's fuzz and invariant testing.
+use std::path::PathBuf;
+
+/// Configuration for fuzz testing.
+#[derive(Clone, Debug, Default)]
+pub struct FuzzConfig {
+    /// Configuration for dictionary-based calldata generation.
+    pub dictionary: FuzzDictionaryConfig,
+    /// Configuration for the coverage-guided fuzz corpus.
+    pub corpus: FuzzCorpusConfig,
+}
+
+/// Configuration for dictionary-based calldata generation.
+#[derive(Clone, Debug)]
+pub struct FuzzDictionaryConfig {
+    /// Chance out of 100 of sampling calldata from the dictionary instead of generating it fresh.
+    pub dictionary_weight: u32,
+}
+
+impl Default for FuzzDictionaryConfig {
+    fn default() -> Self {
+        Self { dictionary_weight: 40 }
+    }
+}
+
+/// Configuration for the coverage-guided fuzz corpus.
+#[derive(Clone, Debug, Default)]
+pub struct FuzzCorpusConfig {
+    /// Whether to maintain a coverage-guided corpus of interesting calldata while fuzzing.
+    pub collect: bool,
+    /// Chance out of 100 of mutating a corpus entry instead of sampling fresh calldata, once the
+    /// corpus is non-empty. `0` disables corpus-guided mutation entirely.
+    pub corpus_mutation_weight: u32,
+    /// Directory used to persist the corpus and counterexamples between runs. `None` disables
+    /// persistence.
+    pub dir: Option<PathBuf>,
+}
@@ -0,0 +1,255 @@
+//! A coverage-guided corpus of "interesting" calldata, used to bias [`FuzzedExecutor::fuzz`]
+//! towards inputs that are more likely to uncover new execution paths, similar to the
+//! pool-based feedback loop used by greybox fuzzers like `fuzzcheck` or AFL.
+use crate::coverage::HitMaps;
+use ethers::types::Bytes;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A single calldata input kept around because it exercised at least one edge that was either
+/// unseen before, or rarer than average across the rest of the corpus.
+///
+/// Derives [`Serialize`]/[`Deserialize`] so entries can be persisted to disk and replayed across
+/// runs; see [`crate::fuzz::persist`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CorpusEntry {
+    /// The calldata that produced the coverage this entry was inserted for.
+    pub calldata: Bytes,
+    /// Selection weight: the reciprocal of how many previously observed inputs had already hit
+    /// the rarest edge this input hit (floored at 1). Every observed input counts, whether or not
+    /// it was kept in the corpus. Higher means "pick me more often".
+    pub score: f64,
+}
+
+/// Tracks every EVM code edge seen so far across a fuzz run, plus the pool of calldata that
+/// discovered them.
+///
+/// An "edge" is identified the same way [`HitMaps`] already tracks coverage: by the hash of the
+/// bytecode it belongs to and the program counter within it. Nothing here mutates the VM; this
+/// only decides which calldata is worth mutating and replaying on later iterations.
+#[derive(Clone, Debug, Default)]
+pub struct Corpus {
+    edge_counts: BTreeMap<(alloy_primitives::B256, usize), u32>,
+    entries: Vec<CorpusEntry>,
+}
+
+impl Corpus {
+    /// Diffs `hits` against every edge seen so far. If `calldata` hit a previously-unseen edge,
+    /// or hit an edge that is rarer than the corpus average, it's inserted as a new entry.
+    pub fn observe(&mut self, calldata: &Bytes, hits: &HitMaps) {
+        let mut new_edges = 0u32;
+        let mut rarest = u32::MAX;
+        for (hash, hit_map) in hits.0.iter() {
+            for pc in hit_map.hits.keys() {
+                let count = self.edge_counts.entry((*hash, *pc)).or_insert(0);
+                if *count == 0 {
+                    new_edges += 1;
+                }
+                rarest = rarest.min(*count);
+                *count += 1;
+            }
+        }
+
+        if rarest == u32::MAX {
+            // `hits` covered no edges at all; nothing to score this input against.
+            return
+        }
+
+        let average = if self.edge_counts.is_empty() {
+            0
+        } else {
+            self.edge_counts.values().sum::<u32>() / self.edge_counts.len() as u32
+        };
+
+        if new_edges > 0 || rarest < average {
+            let coverers = rarest.max(1) as f64;
+            self.entries.push(CorpusEntry { calldata: calldata.clone(), score: 1.0 / coverers });
+        }
+    }
+
+    /// Picks a corpus entry, weighted by [`CorpusEntry::score`] so inputs covering rarer edges
+    /// are favored. Returns `None` if the corpus is empty.
+    pub fn weighted_pick<R: Rng>(&self, rng: &mut R) -> Option<&CorpusEntry> {
+        self.entries.choose_weighted(rng, |entry| entry.score).ok()
+    }
+
+    /// Number of inputs currently kept in the corpus.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of distinct edges observed across the whole fuzz run so far.
+    pub fn unique_edges(&self) -> usize {
+        self.edge_counts.len()
+    }
+
+    pub fn entries(&self) -> &[CorpusEntry] {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(calldata: &[u8], score: f64) -> CorpusEntry {
+        CorpusEntry { calldata: calldata.to_vec().into(), score }
+    }
+
+    #[test]
+    fn weighted_pick_is_none_on_empty_corpus() {
+        let corpus = Corpus::default();
+        assert!(corpus.weighted_pick(&mut rand::thread_rng()).is_none());
+    }
+
+    #[test]
+    fn weighted_pick_favors_higher_score() {
+        let corpus = Corpus {
+            edge_counts: BTreeMap::new(),
+            entries: vec![entry(&[1], 0.0), entry(&[2], 1.0)],
+        };
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let picked = corpus.weighted_pick(&mut rng).unwrap();
+            assert_eq!(picked.calldata.0.as_ref(), &[2][..]);
+        }
+    }
+
+    #[test]
+    fn len_and_unique_edges_reflect_entries_and_edge_counts() {
+        let mut corpus = Corpus::default();
+        assert_eq!(corpus.len(), 0);
+        assert_eq!(corpus.unique_edges(), 0);
+        corpus.entries.push(entry(&[1], 1.0));
+        assert_eq!(corpus.len(), 1);
+        assert!(!corpus.is_empty());
+        corpus.edge_counts.insert((alloy_primitives::B256::ZERO, 0), 1);
+        assert_eq!(corpus.unique_edges(), 1);
+    }
+}
+
+/// Mutation operators applied to corpus entries to derive new candidate calldata, rather than
+/// sampling fresh random calldata from the proptest strategy.
+pub mod mutate {
+    use super::CorpusEntry;
+    use ethers::types::Bytes;
+    use rand::{seq::SliceRandom, Rng};
+
+    /// Flips a handful of random bits in `calldata`, leaving the 4-byte function selector alone.
+    pub fn byte_flip<R: Rng>(calldata: &Bytes, rng: &mut R) -> Bytes {
+        let mut data = calldata.0.to_vec();
+        if data.len() > 4 {
+            let flips = rng.gen_range(1..=3.min(data.len() - 4));
+            for _ in 0..flips {
+                let idx = 4 + rng.gen_range(0..data.len() - 4);
+                data[idx] ^= 1 << rng.gen_range(0..8);
+            }
+        }
+        data.into()
+    }
+
+    /// Mutates a single ABI-encoded 32-byte word chosen at random, so the rest of the calldata
+    /// keeps decoding to the same argument shape.
+    pub fn word_mutate<R: Rng>(calldata: &Bytes, rng: &mut R) -> Bytes {
+        let mut data = calldata.0.to_vec();
+        let words = data.len().saturating_sub(4) / 32;
+        if words > 0 {
+            let start = 4 + rng.gen_range(0..words) * 32;
+            match rng.gen_range(0..3) {
+                0 => data[start..start + 32].fill(0),
+                1 => data[start..start + 32].fill(0xff),
+                _ => rng.fill(&mut data[start..start + 32]),
+            }
+        }
+        data.into()
+    }
+
+    /// Splices the tail of `donor` onto the head of `base`, crossing over two corpus entries the
+    /// way genetic fuzzers like AFL do.
+    pub fn splice<R: Rng>(base: &Bytes, donor: &Bytes, rng: &mut R) -> Bytes {
+        if base.0.len() <= 4 || donor.0.len() <= 4 {
+            return base.clone()
+        }
+        let cut = 4 + rng.gen_range(0..base.0.len() - 4).min(donor.0.len() - 4);
+        let mut data = base.0[..cut].to_vec();
+        data.extend_from_slice(&donor.0[cut.min(donor.0.len())..]);
+        data.into()
+    }
+
+    /// Applies a randomly chosen mutation to a randomly chosen corpus entry, splicing against a
+    /// second entry when the corpus has more than one candidate.
+    ///
+    /// Callers must only invoke this with a non-empty `entries`; any other defect in the corpus
+    /// (e.g. a hand-edited or persisted entry with a non-positive `score`) falls back to a
+    /// uniform pick instead of panicking, the same way `persist`'s loaders skip corrupt entries
+    /// rather than taking the whole fuzz run down.
+    pub fn mutate_entry<R: Rng>(entries: &[CorpusEntry], rng: &mut R) -> Bytes {
+        let base = &pick(entries, rng).calldata;
+        let operators = if entries.len() > 1 { 3 } else { 2 };
+        match rng.gen_range(0..operators) {
+            0 => byte_flip(base, rng),
+            1 => word_mutate(base, rng),
+            _ => splice(base, &pick(entries, rng).calldata, rng),
+        }
+    }
+
+    /// Picks an entry weighted by score, falling back to a uniform pick when every candidate's
+    /// weight is non-positive.
+    fn pick<'a, R: Rng>(entries: &'a [CorpusEntry], rng: &mut R) -> &'a CorpusEntry {
+        entries
+            .choose_weighted(rng, |entry| entry.score.max(0.0))
+            .ok()
+            .or_else(|| entries.choose(rng))
+            .expect("corpus must be non-empty")
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn entry(calldata: &[u8]) -> CorpusEntry {
+            CorpusEntry { calldata: calldata.to_vec().into(), score: 1.0 }
+        }
+
+        #[test]
+        fn byte_flip_preserves_selector_and_length() {
+            let mut rng = rand::thread_rng();
+            let calldata = entry(&[0xde, 0xad, 0xbe, 0xef, 1, 2, 3, 4]).calldata;
+            let mutated = byte_flip(&calldata, &mut rng);
+            assert_eq!(mutated.len(), calldata.len());
+            assert_eq!(&mutated.0[..4], &calldata.0[..4]);
+        }
+
+        #[test]
+        fn word_mutate_preserves_word_count() {
+            let mut rng = rand::thread_rng();
+            let calldata: Bytes = vec![0u8; 4 + 32 * 2].into();
+            let mutated = word_mutate(&calldata, &mut rng);
+            assert_eq!(mutated.len(), calldata.len());
+        }
+
+        #[test]
+        fn splice_falls_back_on_short_inputs() {
+            let mut rng = rand::thread_rng();
+            let short: Bytes = vec![1, 2, 3].into();
+            let long: Bytes = vec![0u8; 36].into();
+            assert_eq!(splice(&short, &long, &mut rng), short);
+        }
+
+        #[test]
+        fn mutate_entry_handles_all_zero_scores_without_panicking() {
+            let mut rng = rand::thread_rng();
+            let entries = vec![
+                CorpusEntry { score: 0.0, ..entry(&[1, 2, 3, 4, 5, 6]) },
+                CorpusEntry { score: 0.0, ..entry(&[1, 2, 3, 4]) },
+            ];
+            // Must not panic even though every weight is non-positive.
+            let _ = mutate_entry(&entries, &mut rng);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Errors `single_fuzz` can raise for a single fuzz case, and the handful of constants used to
+//! recognize them once they've round-tripped through proptest's `Reason`/`TestCaseError` types.
+use thiserror::Error;
+
+/// The data a successful `vm.assume(false)` call returns, used to recognize a rejected case from
+/// its raw call result before it's turned into a [`FuzzError::AssumeReject`].
+pub const ASSUME_MAGIC_RETURN_CODE: &[u8] = b"FOUNDRY::ASSUME";
+
+/// Reasons a single fuzz case can be rejected or recoverably failed.
+///
+/// Derives `Ord`/`Eq` so it can be used as a [`std::collections::BTreeMap`] key for tallying how
+/// often each reason occurs across a run; see `rejects_by_reason` on
+/// [`FuzzTestResult`](super::FuzzTestResult).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Error)]
+pub enum FuzzError {
+    /// `vm.assume` rejected more cases over the whole run than the runner's global reject limit
+    /// (the carried value) allows, aborting the run.
+    #[error("too many global rejects ({0} allowed)")]
+    TooManyRejects(u32),
+    /// `vm.assume` rejected this particular case.
+    #[error("`vm.assume` rejected this case")]
+    AssumeReject,
+    /// The executor returned an error instead of a call result for the fuzzed call. A revert is
+    /// not reported this way; it yields a call result like any other outcome.
+    #[error("fuzz test call failed")]
+    FailedContractCall,
+    /// The call produced no state changeset to collect dictionary values from.
+    #[error("fuzzed call had no state changeset")]
+    EmptyChangeset,
+    /// The target address has no known contract/ABI associated with it.
+    #[error("unknown contract")]
+    UnknownContract,
+    /// The target ABI has no function matching the fuzzed selector.
+    #[error("unknown function")]
+    UnknownFunction,
+    /// The fuzzed calldata failed to decode against the target function's inputs.
+    #[error("failed to decode fuzzed input")]
+    FailedDecodeInput,
+}
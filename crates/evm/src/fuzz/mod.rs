@@ -7,6 +7,7 @@ use crate::{
     utils::{b160_to_h160, h160_to_b160},
 };
 use alloy_primitives::U256;
+use corpus::{mutate::mutate_entry, Corpus};
 use error::{FuzzError, ASSUME_MAGIC_RETURN_CODE};
 use ethers::{
     abi::{Abi, Function, Token},
@@ -16,20 +17,72 @@ use eyre::Result;
 use foundry_common::{calc, contracts::ContractsByAddress};
 use foundry_config::FuzzConfig;
 pub use proptest::test_runner::Reason;
-use proptest::test_runner::{TestCaseError, TestError, TestRunner};
+use proptest::{
+    strategy::{NewTree, Strategy, ValueTree},
+    test_runner::{TestCaseError, TestError, TestRunner},
+};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, collections::BTreeMap, fmt};
+use stats::{FuzzStats, STATS_EVERY_N_CASES};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt,
+    rc::Rc,
+    sync::mpsc::Sender,
+    time::Instant,
+};
 use strategies::{
     build_initial_state, collect_state_from_call, fuzz_calldata, fuzz_calldata_from_state,
     EvmFuzzState,
 };
 use types::{CaseOutcome, CounterExampleOutcome, FuzzCase, FuzzOutcome};
 
+pub mod corpus;
 pub mod error;
 pub mod invariant;
+pub mod persist;
+pub mod stats;
 pub mod strategies;
 pub mod types;
 
+/// Recovers the [`FuzzError`] that `single_fuzz` raised from the rendered message a
+/// [`TestCaseError`] carries, since that's all proptest preserves once the error crosses into its
+/// own `Reason` type. `single_fuzz` only ever rejects or recoverably fails with one of these three
+/// reasons, so an unrecognized message is simply not tallied.
+fn classify_single_fuzz_error(message: &str) -> Option<FuzzError> {
+    [FuzzError::AssumeReject, FuzzError::FailedContractCall, FuzzError::EmptyChangeset]
+        .into_iter()
+        .find(|reason| reason.to_string() == message)
+}
+
+/// Strategy that, with some probability, replays a mutation of a [`Corpus`] entry instead of
+/// sampling fresh calldata from `fallback`. This is what turns `fuzz` greybox: once a run has
+/// found coverage-worthy calldata, later iterations can lean on it rather than generating blind.
+#[derive(Debug)]
+struct CorpusStrategy<S> {
+    fallback: S,
+    corpus: Rc<RefCell<Corpus>>,
+    /// Chance out of 100 of drawing from `corpus` instead of `fallback`, once it's non-empty.
+    corpus_weight: u32,
+}
+
+impl<S: Strategy<Value = Bytes>> Strategy for CorpusStrategy<S> {
+    type Tree = Box<dyn ValueTree<Value = Bytes>>;
+    type Value = Bytes;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let corpus = self.corpus.borrow();
+        if !corpus.is_empty() && runner.rng().gen_ratio(self.corpus_weight.min(100), 100) {
+            let calldata = mutate_entry(corpus.entries(), runner.rng());
+            drop(corpus);
+            return Ok(Box::new(proptest::strategy::Just(calldata)))
+        }
+        drop(corpus);
+        self.fallback.new_tree(runner).map(|tree| Box::new(tree) as Box<dyn ValueTree<Value = Bytes>>)
+    }
+}
+
 /// Wrapper around an [`Executor`] which provides fuzzing support using [`proptest`](https://docs.rs/proptest/1.0.0/proptest/).
 ///
 /// After instantiation, calling `fuzz` will proceed to hammer the deployed smart contract with
@@ -68,6 +121,31 @@ impl<'a> FuzzedExecutor<'a> {
         address: Address,
         should_fail: bool,
         errors: Option<&Abi>,
+    ) -> FuzzTestResult {
+        self.fuzz_inner(func, address, should_fail, errors, None)
+    }
+
+    /// Like [`Self::fuzz`], but periodically pushes a [`FuzzStats`] snapshot to `stats` so a
+    /// caller can render a live view of throughput, rejections and coverage growth while the run
+    /// is still in progress, instead of waiting for the final [`FuzzTestResult`].
+    pub fn fuzz_with_stats(
+        &self,
+        func: &Function,
+        address: Address,
+        should_fail: bool,
+        errors: Option<&Abi>,
+        stats: Sender<FuzzStats>,
+    ) -> FuzzTestResult {
+        self.fuzz_inner(func, address, should_fail, errors, Some(stats))
+    }
+
+    fn fuzz_inner(
+        &self,
+        func: &Function,
+        address: Address,
+        should_fail: bool,
+        errors: Option<&Abi>,
+        stats: Option<Sender<FuzzStats>>,
     ) -> FuzzTestResult {
         // Stores the first Fuzzcase
         let first_case: RefCell<Option<FuzzCase>> = RefCell::default();
@@ -84,8 +162,68 @@ impl<'a> FuzzedExecutor<'a> {
         // Stores coverage information for all fuzz cases
         let coverage: RefCell<Option<HitMaps>> = RefCell::default();
 
+        // Coverage-guided corpus of calldata that discovered new or rare edges. It starts empty,
+        // is seeded from persisted entries below when a corpus directory is configured, and grows
+        // as `single_fuzz` reports back coverage for each case while `collect` is enabled.
+        let corpus = Rc::new(RefCell::new(Corpus::default()));
+
+        // Cases run and cases rejected so far, used to drive `stats` snapshots.
+        let runs: RefCell<u32> = RefCell::default();
+        let rejects: RefCell<u32> = RefCell::default();
+        let found_counterexample: RefCell<bool> = RefCell::default();
+        let start = Instant::now();
+
+        // Tally of every reject/recoverable failure `single_fuzz` has returned so far, by reason.
+        let rejects_by_reason: RefCell<BTreeMap<FuzzError, u64>> = RefCell::default();
+
         let state = self.build_fuzz_state();
 
+        // If a corpus directory is configured, replay persisted counterexamples up front (so a
+        // regressed bug fails fast instead of waiting on proptest to rediscover it) and seed the
+        // in-memory corpus with previously discovered coverage.
+        let mut seeded_entries = 0;
+        if let Some(dir) = &self.config.corpus.dir {
+            for counterexample in persist::load_counterexamples(dir).unwrap_or_default() {
+                if let Ok(FuzzOutcome::CounterExample(outcome)) =
+                    self.single_fuzz(&state, address, should_fail, counterexample.calldata.clone())
+                {
+                    return self.regression_result(outcome, errors, rejects_by_reason.borrow().clone())
+                }
+            }
+
+            // Only load and mutate from the persisted corpus when `collect` is enabled; without
+            // it, `dir` governs the counterexample replay above, which must always run regardless
+            // of whether this particular run is maintaining the corpus, and the saving at the end.
+            if self.config.corpus.collect {
+                // Replay each persisted corpus entry verbatim (rather than just appending it) so
+                // `observe` rebuilds the edge set it originally earned; `edge_counts` starts empty
+                // for this run, so the replayed edges are counted again, and every replayed entry
+                // that hits an edge no earlier replay hit (or one rarer than average) is
+                // re-inserted. If an entry that used to
+                // pass now produces a counterexample, that's a real regression - fail fast on it
+                // the same way the persisted-counterexample replay above does, rather than
+                // silently dropping it.
+                for seeded in persist::load_corpus(dir).unwrap_or_default() {
+                    match self.single_fuzz(&state, address, should_fail, seeded.calldata.clone()) {
+                        Ok(FuzzOutcome::Case(case)) => {
+                            if let Some(hits) = &case.coverage {
+                                corpus.borrow_mut().observe(&seeded.calldata, hits);
+                            }
+                        }
+                        Ok(FuzzOutcome::CounterExample(outcome)) => {
+                            return self.regression_result(
+                                outcome,
+                                errors,
+                                rejects_by_reason.borrow().clone(),
+                            )
+                        }
+                        Err(_) => {}
+                    }
+                }
+                seeded_entries = corpus.borrow().len();
+            }
+        }
+
         let mut weights = vec![];
         let dictionary_weight = self.config.dictionary.dictionary_weight.min(100);
         if self.config.dictionary.dictionary_weight < 100 {
@@ -99,48 +237,71 @@ impl<'a> FuzzedExecutor<'a> {
         }
 
         let strat = proptest::strategy::Union::new_weighted(weights);
+        let strat = CorpusStrategy {
+            fallback: strat,
+            corpus: corpus.clone(),
+            // `collect` gates corpus-guided mutation too: a corpus that isn't being maintained
+            // this run shouldn't still be sampled from.
+            corpus_weight: if self.config.corpus.collect {
+                self.config.corpus.corpus_mutation_weight
+            } else {
+                0
+            },
+        };
         debug!(func = ?func.name, should_fail, "fuzzing");
         let run_result = self.runner.clone().run(&strat, |calldata| {
-            let fuzz_res = self.single_fuzz(&state, address, should_fail, calldata)?;
-
-            match fuzz_res {
-                FuzzOutcome::Case(case) => {
-                    let mut first_case = first_case.borrow_mut();
-                    gas_by_case.borrow_mut().push((case.case.gas, case.case.stipend));
-                    if first_case.is_none() {
-                        first_case.replace(case.case);
+            *runs.borrow_mut() += 1;
+
+            let outcome = match self.single_fuzz(&state, address, should_fail, calldata) {
+                Ok(fuzz_res) => self.record_case(
+                    fuzz_res,
+                    &corpus,
+                    &first_case,
+                    &gas_by_case,
+                    &traces,
+                    &coverage,
+                    &counterexample,
+                    &found_counterexample,
+                    errors,
+                ),
+                Err(err) => {
+                    if matches!(err, TestCaseError::Reject(_)) {
+                        *rejects.borrow_mut() += 1;
                     }
-
-                    traces.replace(case.traces);
-
-                    if let Some(prev) = coverage.take() {
-                        // Safety: If `Option::or` evaluates to `Some`, then `call.coverage` must
-                        // necessarily also be `Some`
-                        coverage.replace(Some(prev.merge(case.coverage.unwrap())));
-                    } else {
-                        coverage.replace(case.coverage);
+                    let message = match &err {
+                        TestCaseError::Fail(reason) | TestCaseError::Reject(reason) => {
+                            reason.message()
+                        }
+                    };
+                    if let Some(reason) = classify_single_fuzz_error(message) {
+                        *rejects_by_reason.borrow_mut().entry(reason).or_insert(0) += 1;
                     }
-
-                    Ok(())
+                    Err(err)
                 }
-                FuzzOutcome::CounterExample(CounterExampleOutcome {
-                    exit_reason,
-                    counterexample: _counterexample,
-                    ..
-                }) => {
-                    let status = exit_reason;
-                    // We cannot use the calldata returned by the test runner in `TestError::Fail`,
-                    // since that input represents the last run case, which may not correspond with
-                    // our failure - when a fuzz case fails, proptest will try
-                    // to run at least one more case to find a minimal failure
-                    // case.
-                    let call_res = _counterexample.1.result.clone();
-                    *counterexample.borrow_mut() = _counterexample;
-                    Err(TestCaseError::fail(
-                        decode::decode_revert(&call_res, errors, Some(status)).unwrap_or_default(),
-                    ))
+            };
+
+            if let Some(stats_tx) = &stats {
+                if *runs.borrow() % STATS_EVERY_N_CASES == 0 {
+                    let gas = gas_by_case.borrow();
+                    let gas_values: Vec<u64> =
+                        gas.iter().map(|(gas, stipend)| gas.saturating_sub(*stipend)).collect();
+                    let mut sorted = gas_values.clone();
+                    sorted.sort_unstable();
+                    let _ = stats_tx.send(FuzzStats {
+                        runs: *runs.borrow(),
+                        rejects: *rejects.borrow(),
+                        elapsed: start.elapsed(),
+                        unique_edges: corpus.borrow().unique_edges(),
+                        corpus_count: corpus.borrow().len(),
+                        min_gas: sorted.first().copied().unwrap_or_default(),
+                        max_gas: sorted.last().copied().unwrap_or_default(),
+                        median_gas: calc::median_sorted(&sorted),
+                        found_counterexample: *found_counterexample.borrow(),
+                    });
                 }
             }
+
+            outcome
         });
 
         let (calldata, call) = counterexample.into_inner();
@@ -155,6 +316,9 @@ impl<'a> FuzzedExecutor<'a> {
             labeled_addresses: call.labels.into_iter().map(|l| (b160_to_h160(l.0), l.1)).collect(),
             traces: if run_result.is_ok() { traces.into_inner() } else { call.traces.clone() },
             coverage: coverage.into_inner(),
+            corpus_count: corpus.borrow().len(),
+            corpus_unique_edges: corpus.borrow().unique_edges(),
+            rejects_by_reason: rejects_by_reason.into_inner(),
         };
 
         match run_result {
@@ -187,9 +351,124 @@ impl<'a> FuzzedExecutor<'a> {
             _ => {}
         }
 
+        if let Some(dir) = &self.config.corpus.dir {
+            for entry in corpus.borrow().entries().iter().skip(seeded_entries) {
+                if let Err(err) = persist::save_corpus_entry(dir, entry) {
+                    warn!(%err, "failed to persist fuzz corpus entry");
+                }
+            }
+
+            if let Some(CounterExample::Single(example)) = &result.counterexample {
+                if let Err(err) = persist::save_counterexample(dir, example) {
+                    warn!(%err, "failed to persist fuzz counterexample");
+                }
+            }
+        }
+
         result
     }
 
+    /// Folds one `single_fuzz` success into the running accumulators for the current `fuzz_inner`
+    /// call, returning the `Result` that should be handed back to the proptest runner.
+    ///
+    /// Split out of `fuzz_inner`'s closure so the periodic `stats` check runs after every case -
+    /// accepted, rejected, or failed - rather than only after the cases that reach this far.
+    #[allow(clippy::too_many_arguments)]
+    fn record_case(
+        &self,
+        fuzz_res: FuzzOutcome,
+        corpus: &Rc<RefCell<Corpus>>,
+        first_case: &RefCell<Option<FuzzCase>>,
+        gas_by_case: &RefCell<Vec<(u64, u64)>>,
+        traces: &RefCell<Option<CallTraceArena>>,
+        coverage: &RefCell<Option<HitMaps>>,
+        counterexample: &RefCell<(Bytes, RawCallResult)>,
+        found_counterexample: &RefCell<bool>,
+        errors: Option<&Abi>,
+    ) -> Result<(), TestCaseError> {
+        match fuzz_res {
+            FuzzOutcome::Case(case) => {
+                if self.config.corpus.collect {
+                    if let Some(hits) = &case.coverage {
+                        corpus.borrow_mut().observe(&case.case.calldata, hits);
+                    }
+                }
+
+                let mut first_case = first_case.borrow_mut();
+                gas_by_case.borrow_mut().push((case.case.gas, case.case.stipend));
+                if first_case.is_none() {
+                    first_case.replace(case.case);
+                }
+
+                traces.replace(case.traces);
+
+                if let Some(prev) = coverage.take() {
+                    // Safety: If `Option::or` evaluates to `Some`, then `call.coverage` must
+                    // necessarily also be `Some`
+                    coverage.replace(Some(prev.merge(case.coverage.unwrap())));
+                } else {
+                    coverage.replace(case.coverage);
+                }
+
+                Ok(())
+            }
+            FuzzOutcome::CounterExample(CounterExampleOutcome {
+                exit_reason,
+                counterexample: _counterexample,
+                ..
+            }) => {
+                let status = exit_reason;
+                // We cannot use the calldata returned by the test runner in `TestError::Fail`,
+                // since that input represents the last run case, which may not correspond with
+                // our failure - when a fuzz case fails, proptest will try
+                // to run at least one more case to find a minimal failure
+                // case.
+                let call_res = _counterexample.1.result.clone();
+                *counterexample.borrow_mut() = _counterexample;
+                found_counterexample.replace(true);
+                Err(TestCaseError::fail(
+                    decode::decode_revert(&call_res, errors, Some(status)).unwrap_or_default(),
+                ))
+            }
+        }
+    }
+
+    /// Builds the terminal [`FuzzTestResult`] for a regression caught while replaying a persisted
+    /// counterexample or corpus entry up front, before the main proptest run even starts.
+    fn regression_result(
+        &self,
+        outcome: CounterExampleOutcome,
+        errors: Option<&Abi>,
+        rejects_by_reason: BTreeMap<FuzzError, u64>,
+    ) -> FuzzTestResult {
+        let CounterExampleOutcome { exit_reason, counterexample: (calldata, call), .. } = outcome;
+        let reason = decode::decode_revert(&call.result, errors, Some(exit_reason));
+        let traces = call.traces.clone();
+        FuzzTestResult {
+            first_case: FuzzCase::default(),
+            gas_by_case: vec![],
+            success: false,
+            reason,
+            counterexample: Some(CounterExample::Single(BaseCounterExample {
+                sender: None,
+                addr: None,
+                signature: None,
+                contract_name: None,
+                traces: traces.clone(),
+                calldata,
+                args: vec![],
+            })),
+            decoded_logs: decode_console_logs(&call.logs),
+            logs: call.logs,
+            labeled_addresses: call.labels.into_iter().map(|l| (b160_to_h160(l.0), l.1)).collect(),
+            traces,
+            coverage: None,
+            corpus_count: 0,
+            corpus_unique_edges: 0,
+            rejects_by_reason,
+        }
+    }
+
     /// Granular and single-step function that runs only one fuzz and returns either a `CaseOutcome`
     /// or a `CounterExampleOutcome`
     pub fn single_fuzz(
@@ -387,6 +666,17 @@ pub struct FuzzTestResult {
 
     /// Raw coverage info
     pub coverage: Option<HitMaps>,
+
+    /// Number of inputs kept in the coverage-guided corpus, if corpus collection was enabled.
+    pub corpus_count: usize,
+
+    /// Number of distinct EVM code edges observed across the run, if corpus collection was
+    /// enabled.
+    pub corpus_unique_edges: usize,
+
+    /// How many times `single_fuzz` rejected or recoverably failed a case, broken down by
+    /// reason, e.g. to tell an over-constrained `vm.assume` from a genuinely low-yield test.
+    pub rejects_by_reason: BTreeMap<FuzzError, u64>,
 }
 
 impl FuzzTestResult {
@@ -492,3 +782,46 @@ impl FuzzedCases {
         self.lowest().map(|c| c.gas).unwrap_or_default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_single_fuzz_error_recognizes_known_reasons() {
+        assert_eq!(
+            classify_single_fuzz_error(&FuzzError::AssumeReject.to_string()),
+            Some(FuzzError::AssumeReject)
+        );
+        assert_eq!(
+            classify_single_fuzz_error(&FuzzError::FailedContractCall.to_string()),
+            Some(FuzzError::FailedContractCall)
+        );
+        assert_eq!(
+            classify_single_fuzz_error(&FuzzError::EmptyChangeset.to_string()),
+            Some(FuzzError::EmptyChangeset)
+        );
+    }
+
+    #[test]
+    fn classify_single_fuzz_error_ignores_unrecognized_messages() {
+        assert_eq!(classify_single_fuzz_error("some other failure"), None);
+    }
+
+    #[test]
+    fn rejects_by_reason_tallies_by_reason() {
+        let mut rejects_by_reason: BTreeMap<FuzzError, u64> = BTreeMap::new();
+        for message in [
+            FuzzError::AssumeReject.to_string(),
+            FuzzError::AssumeReject.to_string(),
+            FuzzError::FailedContractCall.to_string(),
+        ] {
+            if let Some(reason) = classify_single_fuzz_error(&message) {
+                *rejects_by_reason.entry(reason).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(rejects_by_reason.get(&FuzzError::AssumeReject), Some(&2));
+        assert_eq!(rejects_by_reason.get(&FuzzError::FailedContractCall), Some(&1));
+        assert_eq!(rejects_by_reason.get(&FuzzError::EmptyChangeset), None);
+    }
+}
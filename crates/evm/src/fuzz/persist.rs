@@ -0,0 +1,167 @@
+//! Disk persistence for the coverage-guided [`Corpus`](super::corpus::Corpus) and for
+//! [`BaseCounterExample`](super::BaseCounterExample)s, so that hard-won coverage and known-failing
+//! inputs survive between invocations of [`FuzzedExecutor::fuzz`](super::FuzzedExecutor::fuzz)
+//! instead of being discarded when the process exits.
+use super::{corpus::CorpusEntry, BaseCounterExample};
+use eyre::{Result, WrapErr};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const CORPUS_SUBDIR: &str = "corpus";
+const COUNTEREXAMPLES_SUBDIR: &str = "counterexamples";
+
+/// Writes `entry` to `dir`/corpus as a new, uniquely named file.
+pub fn save_corpus_entry(dir: &Path, entry: &CorpusEntry) -> Result<()> {
+    let dir = dir.join(CORPUS_SUBDIR);
+    fs::create_dir_all(&dir)?;
+    let path = next_path(&dir, "corpus")?;
+    fs::write(&path, serde_json::to_vec(entry)?)
+        .wrap_err_with(|| format!("failed to write corpus entry to {}", path.display()))
+}
+
+/// Loads every corpus entry previously persisted under `dir`/corpus, skipping files that fail to
+/// parse rather than aborting the whole run over one corrupt entry.
+pub fn load_corpus(dir: &Path) -> Result<Vec<CorpusEntry>> {
+    load_all(&dir.join(CORPUS_SUBDIR))
+}
+
+/// Writes `example` to `dir`/counterexamples as a new, uniquely named file.
+pub fn save_counterexample(dir: &Path, example: &BaseCounterExample) -> Result<()> {
+    let dir = dir.join(COUNTEREXAMPLES_SUBDIR);
+    fs::create_dir_all(&dir)?;
+    let path = next_path(&dir, "counterexample")?;
+    fs::write(&path, serde_json::to_vec(example)?)
+        .wrap_err_with(|| format!("failed to write counterexample to {}", path.display()))
+}
+
+/// Loads every counterexample previously persisted under `dir`/counterexamples, so they can be
+/// replayed up front and fail fast if the bug they recorded has reappeared.
+pub fn load_counterexamples(dir: &Path) -> Result<Vec<BaseCounterExample>> {
+    load_all(&dir.join(COUNTEREXAMPLES_SUBDIR))
+}
+
+fn load_all<T: serde::de::DeserializeOwned>(dir: &Path) -> Result<Vec<T>> {
+    if !dir.exists() {
+        return Ok(Vec::new())
+    }
+    let mut out = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue
+        }
+        match fs::read(&path).map_err(Into::into).and_then(|bytes| {
+            serde_json::from_slice::<T>(&bytes).wrap_err("failed to deserialize")
+        }) {
+            Ok(value) => out.push(value),
+            Err(err) => warn!(path = %path.display(), %err, "skipping unreadable persisted fuzz entry"),
+        }
+    }
+    Ok(out)
+}
+
+/// Finds the next unused `<dir>/<prefix>-<n>.json` path, so repeated runs keep appending instead
+/// of clobbering previously persisted entries.
+fn next_path(dir: &Path, prefix: &str) -> Result<PathBuf> {
+    let mut n = fs::read_dir(dir)?.count();
+    loop {
+        let path = dir.join(format!("{prefix}-{n}.json"));
+        if !path.exists() {
+            return Ok(path)
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    /// A fresh, empty scratch directory for a single test, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "foundry-fuzz-persist-test-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn corpus_entry_round_trips_through_disk() {
+        let dir = TempDir::new();
+        let entry = CorpusEntry { calldata: vec![1, 2, 3, 4].into(), score: 0.5 };
+        save_corpus_entry(&dir.0, &entry).unwrap();
+
+        let loaded = load_corpus(&dir.0).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].calldata, entry.calldata);
+        assert_eq!(loaded[0].score, entry.score);
+    }
+
+    #[test]
+    fn multiple_corpus_entries_do_not_clobber_each_other() {
+        let dir = TempDir::new();
+        for i in 0..3u8 {
+            save_corpus_entry(&dir.0, &CorpusEntry { calldata: vec![i].into(), score: 1.0 })
+                .unwrap();
+        }
+        assert_eq!(load_corpus(&dir.0).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn counterexample_round_trips_through_disk() {
+        let dir = TempDir::new();
+        let example = BaseCounterExample {
+            sender: None,
+            addr: None,
+            calldata: vec![0xde, 0xad].into(),
+            signature: Some("foo()".to_string()),
+            contract_name: None,
+            traces: None,
+            args: vec![],
+        };
+        save_counterexample(&dir.0, &example).unwrap();
+
+        let loaded = load_counterexamples(&dir.0).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].calldata, example.calldata);
+        assert_eq!(loaded[0].signature, example.signature);
+    }
+
+    #[test]
+    fn loading_from_a_missing_directory_returns_empty() {
+        let dir = TempDir::new();
+        let missing = dir.0.join("does-not-exist");
+        assert!(load_corpus(&missing).unwrap().is_empty());
+        assert!(load_counterexamples(&missing).unwrap().is_empty());
+    }
+
+    #[test]
+    fn corrupt_entries_are_skipped_not_fatal() {
+        let dir = TempDir::new();
+        let sub = dir.0.join(CORPUS_SUBDIR);
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join("corrupt-0.json"), b"not json").unwrap();
+        save_corpus_entry(&dir.0, &CorpusEntry { calldata: vec![1].into(), score: 1.0 }).unwrap();
+
+        let loaded = load_corpus(&dir.0).unwrap();
+        assert_eq!(loaded.len(), 1);
+    }
+}
@@ -0,0 +1,60 @@
+//! Periodic progress snapshots emitted while [`FuzzedExecutor::fuzz_with_stats`] is running, so a
+//! caller can render a live dashboard instead of waiting silently for the final
+//! [`FuzzTestResult`](super::FuzzTestResult).
+use std::time::Duration;
+
+/// How often a snapshot is pushed to the reporting channel, in completed fuzz cases.
+pub const STATS_EVERY_N_CASES: u32 = 50;
+
+/// A point-in-time snapshot of an in-progress fuzz run.
+#[derive(Clone, Debug, Default)]
+pub struct FuzzStats {
+    /// Number of fuzz cases run so far, including rejected ones.
+    pub runs: u32,
+    /// Cases rejected by `vm.assume` so far. Recoverable failures inside `single_fuzz` are not
+    /// counted here.
+    pub rejects: u32,
+    /// Wall-clock time spent fuzzing so far.
+    pub elapsed: Duration,
+    /// Distinct EVM code edges observed so far, if the coverage-guided corpus is enabled.
+    pub unique_edges: usize,
+    /// Number of calldata inputs currently kept in the coverage-guided corpus.
+    pub corpus_count: usize,
+    /// Lowest gas usage observed so far.
+    pub min_gas: u64,
+    /// Highest gas usage observed so far.
+    pub max_gas: u64,
+    /// Median gas usage of all cases run so far.
+    pub median_gas: u64,
+    /// Whether a counterexample has been found yet.
+    pub found_counterexample: bool,
+}
+
+impl FuzzStats {
+    /// Cases run per second so far, or `0` if no time has elapsed yet.
+    pub fn runs_per_sec(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 {
+            0.0
+        } else {
+            self.runs as f64 / secs
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_per_sec_is_zero_with_no_elapsed_time() {
+        let stats = FuzzStats { runs: 100, elapsed: Duration::ZERO, ..Default::default() };
+        assert_eq!(stats.runs_per_sec(), 0.0);
+    }
+
+    #[test]
+    fn runs_per_sec_divides_runs_by_elapsed_seconds() {
+        let stats = FuzzStats { runs: 200, elapsed: Duration::from_secs(2), ..Default::default() };
+        assert_eq!(stats.runs_per_sec(), 100.0);
+    }
+}